@@ -48,6 +48,8 @@
 //! | `-f, --file <file>` | File | Loads a pact from the given file |
 //! | `-u, --url <url>` | URL | Loads a pact from a URL resource |
 //! | `-d, --dir <dir>` | Directory | Loads all the pacts from the given directory |
+//! | `-b, --broker-url <url>` `--webhook-url <url>` | Webhook callback URL | Loads the single pact version at `--webhook-url` from the broker at `--broker-url`, as passed in a `contract_content_changed` webhook |
+//! | `-b, --broker-url <url>` | Pact Broker | Loads pacts for verification from the broker at `--broker-url`. With no other filtering options, loads the latest pacts for every consumer. Filtering by `--consumer-version-selector <json>` (can be repeated), `--consumer <name>` (can be repeated, shorthand for a selector matching that consumer), `--provider-version-branch <branch>` or `--include-pending` additionally requires `--provider <name>`, since the pacts-for-verification query those filters use is scoped to a single provider |
 //!
 //! ### Server Options
 //!
@@ -56,6 +58,16 @@
 //! | Option | Description |
 //! |--------|-------------|
 //! | `-p, --port <port>` | The port to bind to. If not specified, a random port will be allocated by the operating system. |
+//! | `--compress` `--compress-min-size <bytes>` | Negotiates gzip, deflate or brotli compression with the client's Accept-Encoding header for response bodies at least `--compress-min-size` bytes long (default 860), skipping any pact response that already declares a Content-Encoding. |
+//! | `--admin-port <port>` | Runs a separate admin listener exposing `GET /interactions` and `POST /reload`, letting the loaded pacts be inspected and hot-reloaded without restarting the stub. |
+//!
+//! ### Shell Completions
+//!
+//! Running with `--completions <shell>` (one of `bash`, `zsh`, `fish`, `powershell` or `elvish`) prints a completion script to stdout instead of starting the server.
+//!
+//! ### Config File
+//!
+//! Instead of (or alongside) flags, `--config <file>` loads a TOML or YAML file (selected by its extension) describing the pact sources and server options. Explicit command line flags always override the equivalent value from the config file.
 //!
 
 #![warn(missing_docs)]
@@ -64,11 +76,12 @@ use std::env;
 use std::error::Error;
 use std::fmt::{Display, Formatter};
 use std::fs;
+use std::io;
 use std::path::Path;
 use std::str::FromStr;
 
 use base64::encode;
-use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind};
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind, Shell};
 use clap::crate_version;
 use futures::stream::*;
 use log::*;
@@ -79,10 +92,11 @@ use pact_models::http_utils::HttpAuth;
 use pact_matching::s;
 use pact_verifier::pact_broker::HALClient;
 use regex::Regex;
-use serde_json::Value;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use simplelog::{Config, SimpleLogger, TerminalMode, TermLogger};
 
-use crate::server::ServerHandler;
+use crate::server::{ServerHandler, ServerHandlerConfig};
 
 mod pact_support;
 mod server;
@@ -154,6 +168,44 @@ fn regex_value(v: String) -> Result<(), String> {
     Regex::new(v.as_str()).map(|_| ()).map_err(|e| format!("'{}' is not a valid regular expression: {}", v, e) )
 }
 
+fn size_value(v: String) -> Result<(), String> {
+    v.parse::<usize>().map(|_| ()).map_err(|e| format!("'{}' is not a valid size value: {}", v, e) )
+}
+
+/// A consumer version selector, used to filter the pacts returned by the Pact Broker's
+/// pacts-for-verification endpoint. A selector that specifies neither `latest` nor a version
+/// resolves to all versions for the given tag/branch.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsumerVersionSelector {
+  /// Only select pacts with the given consumer version tag
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tag: Option<String>,
+  /// Only select pacts from the given consumer branch
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub branch: Option<String>,
+  /// Only select pacts from the consumer's main branch
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub main_branch: Option<bool>,
+  /// Only select the latest pact that matches the other criteria
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub latest: Option<bool>,
+  /// Only select pacts for the named consumer
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub consumer: Option<String>,
+  /// Only select pacts that have been deployed or released
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub deployed_or_released: Option<bool>
+}
+
+/// Validates that a `--consumer-version-selector` value is not just syntactically valid JSON,
+/// but deserializes into a [`ConsumerVersionSelector`], so a malformed selector is rejected by
+/// clap up front instead of panicking later when it's parsed again in [`pact_source`].
+fn consumer_version_selector_value(v: String) -> Result<(), String> {
+  serde_json::from_str::<ConsumerVersionSelector>(v.as_str()).map(|_| ())
+    .map_err(|e| format!("'{}' is not a valid consumer version selector: {}", v, e))
+}
+
 /// Source for loading pacts
 #[derive(Debug, Clone)]
 pub enum PactSource {
@@ -163,8 +215,43 @@ pub enum PactSource {
   Dir(String),
   /// Load the pact from a URL
   URL(String, Option<HttpAuth>),
-  /// Load all pacts from a Pact Broker
-  Broker(String, Option<HttpAuth>)
+  /// Load pacts from a Pact Broker, optionally filtered down to the pacts that need
+  /// verifying using consumer version selectors and a provider version branch
+  Broker {
+    /// Base URL of the Pact Broker
+    url: String,
+    /// Authentication to use when contacting the broker
+    auth: Option<HttpAuth>,
+    /// Name of the provider to fetch pacts for verification for. Required when `selectors`,
+    /// `provider_version_branch` or `include_pending` request the filtered
+    /// pacts-for-verification query, since that relation is templated per-provider
+    provider: Option<String>,
+    /// Consumer version selectors to filter the pacts-for-verification query by. An empty
+    /// list, with no `provider_version_branch` and `include_pending` unset, falls back to
+    /// fetching every latest pact
+    selectors: Vec<ConsumerVersionSelector>,
+    /// Only fetch pacts that are verifiable against this provider version branch
+    provider_version_branch: Option<String>,
+    /// Include pacts that are pending verification
+    include_pending: bool
+  },
+  /// Load a single pact from a Pact Broker webhook callback URL (as passed in a
+  /// `contract_content_changed` webhook), authenticating against the broker it came from
+  WebhookCallbackUrl {
+    /// URL of the specific pact version to load
+    pact_url: String,
+    /// Base URL of the Pact Broker to authenticate against
+    broker_url: String,
+    /// Authentication to use when contacting the broker
+    auth: Option<HttpAuth>
+  }
+}
+
+fn http_auth(user: Option<&str>, token: Option<&str>) -> Option<HttpAuth> {
+  user.map(|u| {
+    let mut parts = u.split(':');
+    HttpAuth::User(parts.next().unwrap().to_string(), parts.next().map(|p| p.to_string()))
+  }).or_else(|| token.map(|t| HttpAuth::Token(t.to_string())))
 }
 
 fn pact_source(matches: &ArgMatches) -> Vec<PactSource> {
@@ -176,27 +263,207 @@ fn pact_source(matches: &ArgMatches) -> Vec<PactSource> {
     sources.extend(values.map(|v| PactSource::Dir(v.to_string())).collect::<Vec<PactSource>>());
   }
   if let Some(values) = matches.values_of("url") {
-    sources.extend(values.map(|v| {
-      let auth = matches.value_of("user").map(|u| {
-        let mut auth = u.split(':');
-        HttpAuth::User(auth.next().unwrap().to_string(), auth.next().map(|p| p.to_string()))
-      })
-        .or_else(|| matches.value_of("token").map(|v| HttpAuth::Token(v.to_string())));
-      PactSource::URL(s!(v), auth)
-    }).collect::<Vec<PactSource>>());
+    let auth = http_auth(matches.value_of("user"), matches.value_of("token"));
+    sources.extend(values.map(|v| PactSource::URL(s!(v), auth.clone())).collect::<Vec<PactSource>>());
   }
   if let Some(url) = matches.value_of("broker-url") {
-    let auth = matches.value_of("user").map(|u| {
-      let mut auth = u.split(':');
-      HttpAuth::User(auth.next().unwrap().to_string(), auth.next().map(|p| p.to_string()))
-    }).or_else(|| matches.value_of("token").map(|v| HttpAuth::Token(v.to_string())));
-    debug!("Loading all pacts from Pact Broker at {} using {} authentication", url,
-      auth.clone().map(|auth| auth.to_string()).unwrap_or_else(|| "no".to_string()));
-    sources.push(PactSource::Broker(url.to_string(), auth));
+    let auth = http_auth(matches.value_of("user"), matches.value_of("token"));
+    if let Some(pact_url) = matches.value_of("webhook-url") {
+      debug!("Loading a single pact from Pact Broker webhook callback URL {} using {} authentication", pact_url,
+        auth.clone().map(|auth| auth.to_string()).unwrap_or_else(|| "no".to_string()));
+      sources.push(PactSource::WebhookCallbackUrl {
+        pact_url: pact_url.to_string(),
+        broker_url: url.to_string(),
+        auth
+      });
+    } else {
+      let mut selectors: Vec<ConsumerVersionSelector> = matches.values_of("consumer-version-selector")
+        .map(|values| values.map(|v| serde_json::from_str(v)
+          .expect("consumer version selector was already validated by consumer_version_selector_value")).collect())
+        .unwrap_or_default();
+      if let Some(consumers) = matches.values_of("consumer") {
+        selectors.extend(consumers.map(|name| ConsumerVersionSelector {
+          consumer: Some(name.to_string()),
+          ..ConsumerVersionSelector::default()
+        }));
+      }
+      let provider_version_branch = matches.value_of("provider-version-branch").map(|v| v.to_string());
+      let include_pending = matches.is_present("include-pending");
+      let provider = matches.value_of("provider").map(|v| v.to_string());
+      debug!("Loading pacts from Pact Broker at {} using {} authentication", url,
+        auth.clone().map(|auth| auth.to_string()).unwrap_or_else(|| "no".to_string()));
+      sources.push(PactSource::Broker { url: url.to_string(), auth, provider, selectors, provider_version_branch, include_pending });
+    }
   }
   sources
 }
 
+/// A pact source as it appears in a `--config` file. Converted to a [`PactSource`] via
+/// [`source_config_to_pact_source`] once the file has been parsed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum SourceConfig {
+  /// Load the pact from a pact file
+  File {
+    /// Path to the pact file
+    path: String
+  },
+  /// Load all the pacts from a Directory
+  Dir {
+    /// Path to the directory
+    path: String
+  },
+  /// Load the pact from a URL
+  Url {
+    /// URL of the pact file
+    url: String,
+    /// User and password to authenticate with, in `user:password` form
+    user: Option<String>,
+    /// Bearer token to authenticate with
+    token: Option<String>
+  },
+  /// Load pacts from a Pact Broker
+  Broker {
+    /// Base URL of the Pact Broker
+    url: String,
+    /// User and password to authenticate with, in `user:password` form
+    user: Option<String>,
+    /// Bearer token to authenticate with
+    token: Option<String>,
+    /// Name of the provider to fetch pacts for verification for. Required when `selectors`,
+    /// `provider_version_branch` or `include_pending` are set
+    provider: Option<String>,
+    /// Consumer version selectors to filter the pacts-for-verification query by
+    #[serde(default)]
+    selectors: Vec<ConsumerVersionSelector>,
+    /// Only fetch pacts that are verifiable against this provider version branch
+    provider_version_branch: Option<String>,
+    /// Include pacts that are pending verification
+    #[serde(default)]
+    include_pending: bool
+  },
+  /// Load a single pact from a Pact Broker webhook callback URL
+  WebhookCallbackUrl {
+    /// URL of the specific pact version to load
+    pact_url: String,
+    /// Base URL of the Pact Broker to authenticate against
+    broker_url: String,
+    /// User and password to authenticate with, in `user:password` form
+    user: Option<String>,
+    /// Bearer token to authenticate with
+    token: Option<String>
+  }
+}
+
+fn source_config_to_pact_source(source: &SourceConfig) -> PactSource {
+  match source {
+    SourceConfig::File { path } => PactSource::File(path.clone()),
+    SourceConfig::Dir { path } => PactSource::Dir(path.clone()),
+    SourceConfig::Url { url, user, token } =>
+      PactSource::URL(url.clone(), http_auth(user.as_deref(), token.as_deref())),
+    SourceConfig::Broker { url, user, token, provider, selectors, provider_version_branch, include_pending } =>
+      PactSource::Broker {
+        url: url.clone(),
+        auth: http_auth(user.as_deref(), token.as_deref()),
+        provider: provider.clone(),
+        selectors: selectors.clone(),
+        provider_version_branch: provider_version_branch.clone(),
+        include_pending: *include_pending
+      },
+    SourceConfig::WebhookCallbackUrl { pact_url, broker_url, user, token } =>
+      PactSource::WebhookCallbackUrl {
+        pact_url: pact_url.clone(),
+        broker_url: broker_url.clone(),
+        auth: http_auth(user.as_deref(), token.as_deref())
+      }
+  }
+}
+
+/// Declarative stub server configuration, loaded from the TOML or YAML file given with
+/// `--config`. Any value also supplied on the command line overrides the value from this file.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct StubConfig {
+  /// Pact sources to load
+  #[serde(default)]
+  sources: Vec<SourceConfig>,
+  /// Port to run the stub server on
+  port: Option<u16>,
+  /// Automatically respond to OPTIONS requests and return default CORS headers
+  #[serde(default)]
+  cors: bool,
+  /// Set the CORS Access-Control-Allow-Origin header to the Referer
+  #[serde(default)]
+  cors_referer: bool,
+  /// Disables TLS certificate validation
+  #[serde(default)]
+  insecure_tls: bool,
+  /// Provider state regular expression to filter the responses by
+  provider_state: Option<String>,
+  /// Name of the header parameter containing the provider state to be used in case multiple
+  /// matching interactions are found
+  provider_state_header_name: Option<String>,
+  /// Include empty provider states when filtering with `provider_state`
+  #[serde(default)]
+  empty_provider_state: bool,
+  /// Negotiate compression of response bodies with the client's Accept-Encoding header
+  #[serde(default)]
+  compress: bool,
+  /// Minimum response body size in bytes before it is compressed
+  compress_min_size: Option<usize>,
+  /// Port to run the admin endpoint on, exposing GET /interactions and POST /reload
+  admin_port: Option<u16>,
+  /// Log level (defaults to info)
+  loglevel: Option<String>
+}
+
+fn load_config_file(path: &str) -> Result<StubConfig, PactError> {
+  let contents = fs::read_to_string(path)?;
+  let result = if path.ends_with(".yaml") || path.ends_with(".yml") {
+    serde_yaml::from_str(&contents)
+      .map_err(|err| PactError::new(format!("Failed to parse YAML config file - {}", err)))
+  } else {
+    toml::from_str(&contents)
+      .map_err(|err| PactError::new(format!("Failed to parse TOML config file - {}", err)))
+  };
+  let config: StubConfig = result.map_err(|err: PactError| err.with_path(Path::new(path)))?;
+  validate_config(&config).map_err(|err: PactError| err.with_path(Path::new(path)))?;
+  Ok(config)
+}
+
+/// Validates the config fields that the CLI equivalents get validated by clap (`.validator(...)`
+/// or `.possible_values(...)`), since config-file values never pass through those.
+fn validate_config(config: &StubConfig) -> Result<(), PactError> {
+  if let Some(filter) = &config.provider_state {
+    regex_value(filter.clone()).map_err(PactError::new)?;
+  }
+  if let Some(level) = &config.loglevel {
+    if !["error", "warn", "info", "debug", "trace", "none"].contains(&level.as_str()) {
+      return Err(PactError::new(format!(
+        "'{}' is not a valid log level (must be one of error, warn, info, debug, trace, none)", level
+      )));
+    }
+  }
+  Ok(())
+}
+
+/// Flattens the pacts loaded by [`load_pacts`] into an owned, thread-safe list of interactions
+/// that a [`ServerHandler`] can serve independently of the `Pact`s they came from, each tagged
+/// with the `"<consumer> -> <provider>"` name of the pact it came from. Only valid to call once
+/// `pacts` is known to contain no errors.
+fn extract_interactions(pacts: &[Result<Box<dyn Pact>, PactError>]) -> Vec<crate::server::StubInteraction> {
+  pacts.iter()
+    .map(|p| p.as_ref().unwrap())
+    .flat_map(|pact| {
+      let pact_name = format!("{} -> {}", pact.consumer().name, pact.provider().name);
+      pact.interactions().into_iter().map(move |interaction| crate::server::StubInteraction {
+        interaction: interaction.thread_safe(),
+        pact_name: pact_name.clone()
+      })
+    })
+    .collect()
+}
+
 fn walkdir(dir: &Path, ext: &str) -> Result<Vec<Result<Box<dyn Pact>, PactError>>, PactError> {
   let mut pacts = vec![];
   debug!("Scanning {:?}", dir);
@@ -260,47 +527,99 @@ async fn load_pacts(
         Err(err) => vec![Err(PactError::new(format!("Could not load pacts from directory '{}' - {}", dir, err)))]
       },
       PactSource::URL(url, auth) => vec![pact_from_url(url, auth, insecure_tls).await],
-      PactSource::Broker(url, auth) => {
+      PactSource::Broker { url, auth, provider, selectors, provider_version_branch, include_pending } => {
         let client = HALClient::with_url(url, auth.clone());
-        match client.navigate("pb:latest-pact-versions", &hashmap!{}).await {
-          Ok(client) => {
-            match client.clone().iter_links("pb:pacts") {
-              Ok(links) => {
-                futures::stream::iter(links.iter().map(|link| (link.clone(), client.clone())))
-                  .then(|(link, client)| {
-                    async move {
-                      client.clone().fetch_url(&link, &hashmap!{}).await
-                        .map_err(|err| PactError::new(err.to_string()))
-                        .and_then(|json| {
-                          let pact_title = link.title.clone().unwrap_or_else(|| link.href.clone().unwrap_or_default());
-                          debug!("Found pact {}", pact_title);
-                          load_pact_from_json(link.href.clone().unwrap_or_default().as_str(), &json)
-                            .map_err(|err|
-                              PactError::new(format!("Error loading \"{}\" ({}) - {}", pact_title, link.href.unwrap_or_default(), err))
-                            )
-                        })
-                    }
-                  })
-                  .collect().await
-              },
-              Err(err) => vec![Err(PactError::new(err.to_string()))]
+        if selectors.is_empty() && provider_version_branch.is_none() && !include_pending {
+          // No filtering was requested, so fall back to today's behaviour of fetching
+          // every latest pact from the broker.
+          match client.navigate("pb:latest-pact-versions", &hashmap!{}).await {
+            Ok(client) => {
+              match client.clone().iter_links("pb:pacts") {
+                Ok(links) => {
+                  futures::stream::iter(links.iter().map(|link| (link.clone(), client.clone())))
+                    .then(|(link, client)| {
+                      async move {
+                        client.clone().fetch_url(&link, &hashmap!{}).await
+                          .map_err(|err| PactError::new(err.to_string()))
+                          .and_then(|json| {
+                            let pact_title = link.title.clone().unwrap_or_else(|| link.href.clone().unwrap_or_default());
+                            debug!("Found pact {}", pact_title);
+                            load_pact_from_json(link.href.clone().unwrap_or_default().as_str(), &json)
+                              .map_err(|err|
+                                PactError::new(format!("Error loading \"{}\" ({}) - {}", pact_title, link.href.unwrap_or_default(), err))
+                              )
+                          })
+                      }
+                    })
+                    .collect().await
+                },
+                Err(err) => vec![Err(PactError::new(err.to_string()))]
+              }
             }
+            Err(err) => vec![Err(PactError::new(err.to_string()))]
+          }
+        } else if let Some(provider) = provider {
+          let body = json!({
+            "consumerVersionSelectors": selectors,
+            "providerVersionBranch": provider_version_branch,
+            "includePendingStatus": include_pending
+          });
+          // The pacts-for-verification relation is templated per-provider, so navigate to the
+          // provider-scoped resource before posting the filter.
+          match client.navigate("pb:provider", &hashmap!{"provider".to_string() => provider.clone()}).await {
+            Ok(client) => {
+              match client.clone().post("pb:provider-pacts-for-verification", &body).await {
+                Ok(doc) => {
+                  let pacts = doc["_embedded"]["pacts"].as_array().cloned().unwrap_or_default();
+                  futures::stream::iter(pacts.into_iter().map(|pact| (pact, client.clone())))
+                    .then(|(pact, client)| {
+                      async move {
+                        let href = pact["href"].as_str().unwrap_or_default().to_string();
+                        let pending = pact["verificationProperties"]["pending"].as_bool().unwrap_or(false);
+                        if pending {
+                          debug!("Pact at {} is pending verification, loading it anyway", href);
+                        }
+                        client.clone().fetch(&href).await
+                          .map_err(|err| PactError::new(err.to_string()))
+                          .and_then(|json| load_pact_from_json(href.as_str(), &json)
+                            .map_err(|err| PactError::new(format!("Error loading \"{}\" - {}", href, err))))
+                      }
+                    })
+                    .collect().await
+                },
+                Err(err) => vec![Err(PactError::new(err.to_string()))]
+              }
+            },
+            Err(err) => vec![Err(PactError::new(err.to_string()))]
           }
-          Err(err) => vec![Err(PactError::new(err.to_string()))]
+        } else {
+          vec![Err(PactError::new(
+            "--provider is required when using --consumer-version-selector, --consumer, \
+            --provider-version-branch or --include-pending".to_string()
+          ))]
         }
       }
+      PactSource::WebhookCallbackUrl { pact_url, broker_url, auth } => {
+        let client = HALClient::with_url(broker_url, auth.clone());
+        vec![
+          client.fetch(pact_url).await
+            .map_err(|err| PactError::new(err.to_string()))
+            .and_then(|json| {
+              debug!("Fetched pact from webhook callback URL {}", pact_url);
+              load_pact_from_json(pact_url, &json).map_err(PactError::new)
+            })
+        ]
+      }
     };
     futures::stream::iter(val)
   }).flatten().collect().await
 }
 
-async fn handle_command_args() -> Result<(), i32> {
-  let args: Vec<String> = env::args().collect();
-  let program = args[0].clone();
-
-  let version = format!("v{}", crate_version!());
-  let app = App::new(program)
-    .version(version.as_str())
+/// Builds the clap `App` definition used both to parse the real command line and to generate
+/// shell completion scripts, so the two can never drift apart.
+fn build_app<'a, 'b>(program: &'b str, version: &'b str) -> App<'a, 'b> {
+  App::new(program)
+    .version(version)
     .about("Pact Stub Server")
     .version_short("v")
     .setting(AppSettings::ArgRequiredElseHelp)
@@ -312,10 +631,26 @@ async fn handle_command_args() -> Result<(), i32> {
       .use_delimiter(false)
       .possible_values(&["error", "warn", "info", "debug", "trace", "none"])
       .help("Log level (defaults to info)"))
+    .arg(Arg::with_name("completions")
+      .long("completions")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .possible_values(&Shell::variants())
+      .help("Print a shell completion script for the given shell to stdout and exit"))
+    .arg(Arg::with_name("config")
+      .short("c")
+      .long("config")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .empty_values(false)
+      .help("TOML or YAML file (selected by extension) describing pact sources and server \
+      options; explicit command line flags override values from this file"))
     .arg(Arg::with_name("file")
       .short("f")
       .long("file")
-      .required_unless_one(&["dir", "url", "broker-url"])
+      .required_unless_one(&["dir", "url", "broker-url", "completions", "config"])
       .takes_value(true)
       .use_delimiter(false)
       .multiple(true)
@@ -325,7 +660,7 @@ async fn handle_command_args() -> Result<(), i32> {
     .arg(Arg::with_name("dir")
       .short("d")
       .long("dir")
-      .required_unless_one(&["file", "url", "broker-url"])
+      .required_unless_one(&["file", "url", "broker-url", "completions", "config"])
       .takes_value(true)
       .use_delimiter(false)
       .multiple(true)
@@ -344,7 +679,7 @@ async fn handle_command_args() -> Result<(), i32> {
     .arg(Arg::with_name("url")
       .short("u")
       .long("url")
-      .required_unless_one(&["file", "dir", "broker-url"])
+      .required_unless_one(&["file", "dir", "broker-url", "completions", "config"])
       .takes_value(true)
       .use_delimiter(false)
       .multiple(true)
@@ -355,13 +690,73 @@ async fn handle_command_args() -> Result<(), i32> {
       .short("b")
       .long("broker-url")
       .env("PACT_BROKER_BASE_URL")
-      .required_unless_one(&["file", "dir", "url"])
+      .required_unless_one(&["file", "dir", "url", "completions", "config"])
       .takes_value(true)
       .use_delimiter(false)
       .multiple(false)
       .number_of_values(1)
       .empty_values(false)
       .help("URL of the pact broker to fetch pacts from"))
+    .arg(Arg::with_name("webhook-url")
+      .long("webhook-url")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("broker-url")
+      .conflicts_with_all(&["provider", "consumer-version-selector", "provider-version-branch",
+      "include-pending", "consumer"])
+      .help("URL of a single pact version to load, as provided by a Pact Broker \
+      `contract_content_changed` webhook callback (requires --broker-url, conflicts with the \
+      pacts-for-verification filtering options --provider, --consumer-version-selector, \
+      --consumer, --provider-version-branch and --include-pending)"))
+    .arg(Arg::with_name("provider")
+      .long("provider")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("broker-url")
+      .help("Name of the provider to fetch pacts for verification for from the Pact Broker \
+      (required when using --consumer-version-selector, --consumer, --provider-version-branch \
+      or --include-pending, requires --broker-url)"))
+    .arg(Arg::with_name("consumer-version-selector")
+      .long("consumer-version-selector")
+      .takes_value(true)
+      .use_delimiter(false)
+      .multiple(true)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("broker-url")
+      .validator(consumer_version_selector_value)
+      .help("Consumer version selector (as JSON) to use when fetching pacts for verification \
+      from the Pact Broker, e.g. '{\"mainBranch\": true}' (can be repeated, requires --broker-url)"))
+    .arg(Arg::with_name("provider-version-branch")
+      .long("provider-version-branch")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("broker-url")
+      .help("Provider version branch to use when fetching pacts for verification from the \
+      Pact Broker (requires --broker-url)"))
+    .arg(Arg::with_name("include-pending")
+      .long("include-pending")
+      .takes_value(false)
+      .use_delimiter(false)
+      .requires("broker-url")
+      .help("Include pacts that are pending verification when fetching pacts for \
+      verification from the Pact Broker (requires --broker-url)"))
+    .arg(Arg::with_name("consumer")
+      .long("consumer")
+      .takes_value(true)
+      .use_delimiter(false)
+      .multiple(true)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("broker-url")
+      .help("Only fetch pacts for the named consumer when fetching pacts for verification \
+      from the Pact Broker (can be repeated, requires --broker-url)"))
     .arg(Arg::with_name("user")
       .long("user")
       .takes_value(true)
@@ -425,17 +820,74 @@ async fn handle_command_args() -> Result<(), i32> {
       .takes_value(false)
       .use_delimiter(false)
       .requires("provider-state")
-      .help("Include empty provider states when filtering with --provider-state"));
+      .help("Include empty provider states when filtering with --provider-state"))
+    .arg(Arg::with_name("compress")
+      .long("compress")
+      .takes_value(false)
+      .use_delimiter(false)
+      .help("Negotiate gzip, deflate or brotli compression of response bodies with the \
+      client's Accept-Encoding header, for responses whose pact does not already declare a \
+      Content-Encoding"))
+    .arg(Arg::with_name("compress-min-size")
+      .long("compress-min-size")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .empty_values(false)
+      .requires("compress")
+      .validator(size_value)
+      .help("Minimum response body size in bytes before it is compressed (defaults to 860, \
+      requires --compress)"))
+    .arg(Arg::with_name("admin-port")
+      .long("admin-port")
+      .takes_value(true)
+      .use_delimiter(false)
+      .number_of_values(1)
+      .help("Port to run the admin endpoint on, exposing GET /interactions and POST /reload \
+      for inspecting and hot-reloading the loaded pacts (defaults to disabled)")
+      .validator(integer_value))
+}
 
+async fn handle_command_args() -> Result<(), i32> {
+  let args: Vec<String> = env::args().collect();
+  let program = args[0].clone();
+
+  let version = format!("v{}", crate_version!());
+  let app = build_app(&program, &version);
   let matches = app.get_matches_safe();
   match matches {
     Ok(ref matches) => {
-      let level = matches.value_of("loglevel").unwrap_or("info");
+      if let Some(shell) = matches.value_of("completions") {
+        build_app(&program, &version)
+          .gen_completions_to(&program, shell.parse().unwrap(), &mut io::stdout());
+        return Ok(());
+      }
+
+      let config = match matches.value_of("config").map(load_config_file) {
+        Some(Ok(config)) => Some(config),
+        Some(Err(err)) => {
+          error!("Could not load the config file - {}", err);
+          return Err(4);
+        },
+        None => None
+      };
+
+      let level = matches.value_of("loglevel")
+        .or_else(|| config.as_ref().and_then(|c| c.loglevel.as_deref()))
+        .unwrap_or("info");
       setup_logger(level);
-      let sources = pact_source(matches);
 
-      let pacts = load_pacts(sources, matches.is_present("insecure-tls"),
-        matches.value_of("ext")).await;
+      let mut sources = pact_source(matches);
+      if sources.is_empty() {
+        if let Some(config) = &config {
+          sources = config.sources.iter().map(source_config_to_pact_source).collect();
+        }
+      }
+
+      let insecure_tls = matches.is_present("insecure-tls")
+        || config.as_ref().map(|c| c.insecure_tls).unwrap_or(false);
+      let ext = matches.value_of("ext").map(String::from);
+      let pacts = load_pacts(sources.clone(), insecure_tls, ext.as_deref()).await;
       if pacts.iter().any(|p| p.is_err()) {
         error!("There were errors loading the pact files.");
         for error in pacts.iter()
@@ -448,26 +900,43 @@ async fn handle_command_args() -> Result<(), i32> {
         }
         Err(3)
       } else {
-        let port = matches.value_of("port").unwrap_or("0").parse::<u16>().unwrap();
-        let provider_state = matches.value_of("provider-state")
-            .map(|filter| Regex::new(filter).unwrap());
+        let port = matches.value_of("port").map(|v| v.parse::<u16>().unwrap())
+          .or_else(|| config.as_ref().and_then(|c| c.port))
+          .unwrap_or(0);
+        let provider_state = matches.value_of("provider-state").map(String::from)
+          .or_else(|| config.as_ref().and_then(|c| c.provider_state.clone()))
+          .map(|filter| Regex::new(&filter).unwrap());
         let provider_state_header_name = matches.value_of("provider-state-header-name")
-            .map(String::from);
-        let empty_provider_states = matches.is_present("empty-provider-state");
-        let pacts = pacts.iter()
-          .map(|p| p.as_ref().unwrap())
-          .flat_map(|pact| pact.interactions())
-          .map(|interaction| interaction.thread_safe())
-          .collect();
-        let auto_cors = matches.is_present("cors");
-        let referer = matches.is_present("cors-referer");
-        let server_handler = ServerHandler::new(
-          pacts,
+          .map(String::from)
+          .or_else(|| config.as_ref().and_then(|c| c.provider_state_header_name.clone()));
+        let empty_provider_states = matches.is_present("empty-provider-state")
+          || config.as_ref().map(|c| c.empty_provider_state).unwrap_or(false);
+        let pacts = extract_interactions(&pacts);
+        let auto_cors = matches.is_present("cors")
+          || config.as_ref().map(|c| c.cors).unwrap_or(false);
+        let referer = matches.is_present("cors-referer")
+          || config.as_ref().map(|c| c.cors_referer).unwrap_or(false);
+        let compress = matches.is_present("compress")
+          || config.as_ref().map(|c| c.compress).unwrap_or(false);
+        let compress_min_size = matches.value_of("compress-min-size")
+          .map(|v| v.parse::<usize>().unwrap())
+          .or_else(|| config.as_ref().and_then(|c| c.compress_min_size))
+          .unwrap_or(860);
+        let admin_port = matches.value_of("admin-port").map(|v| v.parse::<u16>().unwrap())
+          .or_else(|| config.as_ref().and_then(|c| c.admin_port));
+        let server_handler = ServerHandler::new(pacts, ServerHandlerConfig {
           auto_cors,
           referer,
           provider_state,
           provider_state_header_name,
-          empty_provider_states);
+          empty_provider_states,
+          compress,
+          compress_min_size,
+          sources,
+          insecure_tls,
+          ext,
+          admin_port
+        });
         tokio::task::spawn_blocking(move || {
           server_handler.start_server(port)
         }).await.unwrap()
@@ -0,0 +1,345 @@
+//! HTTP server that serves stub responses for the loaded pact interactions, with optional CORS,
+//! provider state filtering, negotiated response compression, and a hot-reloadable admin
+//! endpoint (`GET /interactions`, `POST /reload`).
+
+use std::convert::Infallible;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+
+use flate2::Compression;
+use flate2::write::{DeflateEncoder, GzEncoder};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, REFERER};
+use hyper::service::{make_service_fn, service_fn};
+use log::*;
+use pact_matching::models::{Interaction, OptionalBody, RequestResponseInteraction, Response as PactResponse};
+use regex::Regex;
+use serde_json::json;
+use tokio::runtime::Builder;
+
+use crate::{extract_interactions, load_pacts, PactSource};
+
+/// An interaction owned independently of the `Pact` it was loaded from, so it can be held by a
+/// [`ServerHandler`] for the lifetime of the server and swapped out wholesale on reload.
+/// `pact_name` identifies the pact it came from (`"<consumer> -> <provider>"`), so the admin
+/// endpoint can tell an operator which loaded file/URL/broker pact an interaction belongs to.
+pub struct StubInteraction {
+  /// The interaction itself, independent of the `Pact` it was loaded from
+  pub interaction: Box<dyn Interaction + Send>,
+  /// `"<consumer> -> <provider>"` name of the pact this interaction came from
+  pub pact_name: String
+}
+
+/// Options controlling how [`ServerHandler`] serves interactions. Grouped into a struct, rather
+/// than threaded through `new` as positional arguments, now that the server has grown CORS,
+/// provider-state and compression settings alongside the original pact sources.
+#[derive(Clone)]
+pub struct ServerHandlerConfig {
+  /// Automatically respond to OPTIONS requests with default CORS headers
+  pub auto_cors: bool,
+  /// Set the CORS Access-Control-Allow-Origin header to the request's Referer
+  pub referer: bool,
+  /// Provider state regular expression to filter the responses by
+  pub provider_state: Option<Regex>,
+  /// Name of the header parameter containing the provider state to use when multiple
+  /// interactions match
+  pub provider_state_header_name: Option<String>,
+  /// Include empty provider states when filtering with `provider_state`
+  pub empty_provider_states: bool,
+  /// Negotiate gzip/deflate/brotli compression of response bodies with the client's
+  /// Accept-Encoding header
+  pub compress: bool,
+  /// Minimum response body size in bytes before it is compressed
+  pub compress_min_size: usize,
+  /// Pact sources the interactions were originally loaded from
+  pub sources: Vec<PactSource>,
+  /// Disables TLS certificate validation when loading pacts from a URL (broker sources are
+  /// not affected)
+  pub insecure_tls: bool,
+  /// File extension to use when loading pacts from a directory
+  pub ext: Option<String>,
+  /// Port to run the admin endpoint on; `None` disables it
+  pub admin_port: Option<u16>
+}
+
+/// Serves stub responses for a set of pact interactions, which can be swapped out wholesale by
+/// a `POST /reload` to the admin endpoint (if one was configured).
+pub struct ServerHandler {
+  interactions: Arc<RwLock<Vec<StubInteraction>>>,
+  config: ServerHandlerConfig
+}
+
+impl ServerHandler {
+  /// Creates a new server handler for the given set of interactions and options.
+  pub fn new(interactions: Vec<StubInteraction>, config: ServerHandlerConfig) -> ServerHandler {
+    ServerHandler {
+      interactions: Arc::new(RwLock::new(interactions)),
+      config
+    }
+  }
+
+  /// Starts the stub server, blocking until it exits.
+  pub fn start_server(self, port: u16) -> Result<(), i32> {
+    let runtime = Builder::new_multi_thread().enable_all().build()
+      .map_err(|err| { error!("Could not start the async runtime - {}", err); 1 })?;
+    runtime.block_on(self.run(port))
+  }
+
+  async fn run(self, port: u16) -> Result<(), i32> {
+    let admin_port = self.config.admin_port;
+    let handler = Arc::new(self);
+
+    let addr: SocketAddr = ([0, 0, 0, 0], port).into();
+    let stub_handler = handler.clone();
+    let make_svc = make_service_fn(move |_conn| {
+      let handler = stub_handler.clone();
+      async move {
+        Ok::<_, Infallible>(service_fn(move |req| {
+          let handler = handler.clone();
+          async move { Ok::<_, Infallible>(handler.handle_stub_request(req)) }
+        }))
+      }
+    });
+    let server = Server::bind(&addr).serve(make_svc);
+    info!("Stub server started on port {}", server.local_addr().port());
+
+    match admin_port {
+      Some(admin_port) => {
+        let admin_addr: SocketAddr = ([0, 0, 0, 0], admin_port).into();
+        let admin_handler = handler.clone();
+        let make_admin_svc = make_service_fn(move |_conn| {
+          let handler = admin_handler.clone();
+          async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+              let handler = handler.clone();
+              async move { Ok::<_, Infallible>(handler.handle_admin_request(req).await) }
+            }))
+          }
+        });
+        let admin_server = Server::bind(&admin_addr).serve(make_admin_svc);
+        info!("Admin server started on port {}", admin_server.local_addr().port());
+        let (stub_result, admin_result) = tokio::join!(server, admin_server);
+        stub_result.map_err(|err| { error!("Stub server error: {}", err); 2 })?;
+        admin_result.map_err(|err| { error!("Admin server error: {}", err); 2 })
+      },
+      None => server.await.map_err(|err| { error!("Stub server error: {}", err); 2 })
+    }
+  }
+
+  /// Handles a request to the admin server: `GET /interactions` lists the currently loaded
+  /// interactions, `POST /reload` re-runs `load_pacts` over the original sources and swaps them
+  /// in atomically.
+  async fn handle_admin_request(&self, req: Request<Body>) -> Response<Body> {
+    match (req.method(), req.uri().path()) {
+      (&Method::GET, "/interactions") => self.list_interactions(),
+      (&Method::POST, "/reload") => self.reload().await,
+      _ => Response::builder().status(StatusCode::NOT_FOUND).body(Body::from("Not Found")).unwrap()
+    }
+  }
+
+  fn list_interactions(&self) -> Response<Body> {
+    let interactions = self.interactions.read().unwrap();
+    let body = json!(interactions.iter()
+      .filter_map(|i| i.interaction.as_request_response().map(|rr| (i.pact_name.clone(), rr)))
+      .map(|(pact_name, rr)| json!({
+        "description": rr.description,
+        "method": rr.request.method,
+        "path": rr.request.path,
+        "providerStates": rr.provider_states.iter().map(|ps| ps.name.clone()).collect::<Vec<_>>(),
+        "sourcePact": pact_name
+      }))
+      .collect::<Vec<_>>());
+    Response::builder()
+      .status(StatusCode::OK)
+      .header(hyper::header::CONTENT_TYPE, "application/json")
+      .body(Body::from(body.to_string()))
+      .unwrap()
+  }
+
+  async fn reload(&self) -> Response<Body> {
+    info!("Reloading {} pact source(s)", self.config.sources.len());
+    let pacts = load_pacts(self.config.sources.clone(), self.config.insecure_tls, self.config.ext.as_deref()).await;
+    if let Some(errors) = pacts.iter().find(|p| p.is_err()).map(|_| {
+      pacts.iter().filter_map(|p| p.as_ref().err().map(|err| err.to_string())).collect::<Vec<_>>()
+    }) {
+      error!("Failed to reload pacts: {:?}", errors);
+      return Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .header(hyper::header::CONTENT_TYPE, "application/json")
+        .body(Body::from(json!({ "errors": errors }).to_string()))
+        .unwrap();
+    }
+
+    let new_interactions = extract_interactions(&pacts);
+    let count = new_interactions.len();
+    *self.interactions.write().unwrap() = new_interactions;
+    info!("Reloaded {} interaction(s)", count);
+    Response::builder()
+      .status(StatusCode::OK)
+      .header(hyper::header::CONTENT_TYPE, "application/json")
+      .body(Body::from(json!({ "interactions": count }).to_string()))
+      .unwrap()
+  }
+
+  fn handle_stub_request(&self, req: Request<Body>) -> Response<Body> {
+    if self.config.auto_cors && req.method() == Method::OPTIONS {
+      return self.cors_preflight_response(&req);
+    }
+
+    let method = req.method().as_str().to_string();
+    let path = req.uri().path().to_string();
+    let provider_state_header = self.config.provider_state_header_name.as_ref()
+      .and_then(|name| req.headers().get(name))
+      .and_then(|v| v.to_str().ok())
+      .map(String::from);
+
+    let interactions = self.interactions.read().unwrap();
+    let candidates: Vec<RequestResponseInteraction> = interactions.iter()
+      .filter_map(|i| i.interaction.as_request_response())
+      .filter(|rr| rr.request.method.eq_ignore_ascii_case(&method) && rr.request.path == path)
+      .collect();
+
+    match self.select_interaction(candidates, provider_state_header.as_deref()) {
+      Some(rr) => self.build_response(&rr.response, &req),
+      None => {
+        warn!("No matching interaction found for {} {}", method, path);
+        Response::builder()
+          .status(StatusCode::NOT_FOUND)
+          .body(Body::from(format!("No interaction found for {} {}", method, path)))
+          .unwrap()
+      }
+    }
+  }
+
+  fn select_interaction(
+    &self,
+    candidates: Vec<RequestResponseInteraction>,
+    header_state: Option<&str>
+  ) -> Option<RequestResponseInteraction> {
+    let mut filtered = candidates;
+    if let Some(regex) = &self.config.provider_state {
+      filtered.retain(|rr| {
+        rr.provider_states.iter().any(|ps| regex.is_match(&ps.name))
+          || (self.config.empty_provider_states && rr.provider_states.is_empty())
+      });
+    }
+    if filtered.len() > 1 {
+      if let Some(state) = header_state {
+        if let Some(matched) = filtered.iter()
+          .find(|rr| rr.provider_states.iter().any(|ps| ps.name == state)) {
+          return Some(matched.clone());
+        }
+      }
+    }
+    filtered.into_iter().next()
+  }
+
+  fn build_response(&self, pact_response: &PactResponse, req: &Request<Body>) -> Response<Body> {
+    let mut builder = Response::builder().status(pact_response.status);
+    for (name, values) in &pact_response.headers.clone().unwrap_or_default() {
+      for value in values {
+        builder = builder.header(name.as_str(), value.as_str());
+      }
+    }
+    let body = match &pact_response.body {
+      OptionalBody::Present(bytes, _) => bytes.clone(),
+      _ => Vec::new()
+    };
+
+    let mut response = builder.body(Body::from(body.clone())).unwrap();
+    if self.config.auto_cors {
+      self.apply_cors_headers(&mut response, req);
+    }
+    if self.config.compress {
+      self.maybe_compress(&mut response, req, &body);
+    }
+    response
+  }
+
+  fn cors_preflight_response(&self, req: &Request<Body>) -> Response<Body> {
+    let mut response = Response::builder().status(StatusCode::OK).body(Body::empty()).unwrap();
+    self.apply_cors_headers(&mut response, req);
+    response
+  }
+
+  fn apply_cors_headers(&self, response: &mut Response<Body>, req: &Request<Body>) {
+    let origin = if self.config.referer {
+      req.headers().get(REFERER).and_then(|v| v.to_str().ok()).map(String::from)
+    } else {
+      None
+    }.unwrap_or_else(|| "*".to_string());
+    let headers = response.headers_mut();
+    headers.insert(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN,
+      HeaderValue::from_str(&origin).unwrap_or_else(|_| HeaderValue::from_static("*")));
+    headers.insert(hyper::header::ACCESS_CONTROL_ALLOW_METHODS,
+      HeaderValue::from_static("GET, POST, PUT, DELETE, OPTIONS"));
+    headers.insert(hyper::header::ACCESS_CONTROL_ALLOW_HEADERS,
+      HeaderValue::from_static("Content-Type"));
+  }
+
+  /// Negotiates and applies gzip/deflate/brotli compression of the response body, honouring the
+  /// request's Accept-Encoding header. Does nothing if the pact response already set its own
+  /// Content-Encoding, or if the body is smaller than `compress_min_size`.
+  fn maybe_compress(&self, response: &mut Response<Body>, req: &Request<Body>, body: &[u8]) {
+    if body.len() < self.config.compress_min_size || response.headers().contains_key(CONTENT_ENCODING) {
+      return;
+    }
+    let accept_encoding = req.headers().get(ACCEPT_ENCODING)
+      .and_then(|v| v.to_str().ok())
+      .unwrap_or("");
+    if let Some((encoding, compressed)) = negotiate_and_compress(accept_encoding, body) {
+      *response.body_mut() = Body::from(compressed.clone());
+      response.headers_mut().insert(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+      response.headers_mut().insert(CONTENT_LENGTH,
+        HeaderValue::from_str(&compressed.len().to_string()).unwrap());
+    }
+  }
+}
+
+fn negotiate_and_compress(accept_encoding: &str, body: &[u8]) -> Option<(&'static str, Vec<u8>)> {
+  let accepted: Vec<&str> = accept_encoding.split(',')
+    .filter(|v| !is_rejected(v))
+    .map(|v| v.split(';').next().unwrap_or("").trim())
+    .collect();
+  for encoding in ["br", "gzip", "deflate"] {
+    if accepted.iter().any(|a| a.eq_ignore_ascii_case(encoding)) {
+      return compress_body(body, encoding).map(|compressed| (encoding, compressed));
+    }
+  }
+  None
+}
+
+/// An Accept-Encoding entry explicitly refuses its encoding by setting `q=0`.
+fn is_rejected(accept_encoding_entry: &str) -> bool {
+  accept_encoding_entry.split(';').skip(1)
+    .filter_map(|param| param.trim().strip_prefix("q="))
+    .any(|q| q.trim().parse::<f32>() == Ok(0.0))
+}
+
+fn compress_body(body: &[u8], encoding: &str) -> Option<Vec<u8>> {
+  match encoding {
+    "gzip" => {
+      let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+      encoder.write_all(body).ok()?;
+      encoder.finish().ok()
+    },
+    "deflate" => {
+      let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+      encoder.write_all(body).ok()?;
+      encoder.finish().ok()
+    },
+    "br" => {
+      let mut output = Vec::new();
+      {
+        let mut encoder = brotli::CompressorWriter::new(&mut output, 4096, 5, 22);
+        encoder.write_all(body).ok()?;
+      }
+      Some(output)
+    },
+    _ => None
+  }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,91 @@
+use super::*;
+
+#[test]
+fn negotiate_and_compress_prefers_brotli_over_gzip_and_deflate() {
+  let (encoding, _) = negotiate_and_compress("gzip, deflate, br", b"hello world").unwrap();
+  assert_eq!(encoding, "br");
+}
+
+#[test]
+fn negotiate_and_compress_falls_back_when_brotli_is_not_accepted() {
+  let (encoding, _) = negotiate_and_compress("deflate, gzip", b"hello world").unwrap();
+  assert_eq!(encoding, "gzip");
+}
+
+#[test]
+fn negotiate_and_compress_honours_q0_rejection() {
+  let (encoding, _) = negotiate_and_compress("br;q=0, gzip", b"hello world").unwrap();
+  assert_eq!(encoding, "gzip");
+}
+
+#[test]
+fn negotiate_and_compress_returns_none_when_nothing_is_accepted() {
+  assert!(negotiate_and_compress("identity", b"hello world").is_none());
+}
+
+#[test]
+fn compress_body_produces_output_for_each_supported_encoding() {
+  for encoding in ["gzip", "deflate", "br"] {
+    let compressed = compress_body(b"hello world", encoding).unwrap();
+    assert!(!compressed.is_empty());
+  }
+}
+
+#[test]
+fn compress_body_returns_none_for_an_unsupported_encoding() {
+  assert!(compress_body(b"hello world", "identity").is_none());
+}
+
+#[test]
+fn is_rejected_detects_a_zero_quality_value() {
+  assert!(is_rejected("gzip;q=0"));
+  assert!(is_rejected("gzip; q=0.0"));
+  assert!(!is_rejected("gzip;q=0.5"));
+  assert!(!is_rejected("gzip"));
+}
+
+fn handler_with_config(config: ServerHandlerConfig) -> ServerHandler {
+  ServerHandler::new(Vec::new(), config)
+}
+
+fn default_config() -> ServerHandlerConfig {
+  ServerHandlerConfig {
+    auto_cors: true,
+    referer: false,
+    provider_state: None,
+    provider_state_header_name: None,
+    empty_provider_states: false,
+    compress: false,
+    compress_min_size: 860,
+    sources: Vec::new(),
+    insecure_tls: false,
+    ext: None,
+    admin_port: None
+  }
+}
+
+#[test]
+fn apply_cors_headers_defaults_to_a_wildcard_origin() {
+  let handler = handler_with_config(default_config());
+  let req = Request::builder().body(Body::empty()).unwrap();
+  let mut response = Response::builder().body(Body::empty()).unwrap();
+  handler.apply_cors_headers(&mut response, &req);
+  assert_eq!(
+    response.headers().get(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+    "*"
+  );
+}
+
+#[test]
+fn apply_cors_headers_reflects_the_referer_when_configured() {
+  let mut config = default_config();
+  config.referer = true;
+  let handler = handler_with_config(config);
+  let req = Request::builder().header(REFERER, "http://example.com").body(Body::empty()).unwrap();
+  let mut response = Response::builder().body(Body::empty()).unwrap();
+  handler.apply_cors_headers(&mut response, &req);
+  assert_eq!(
+    response.headers().get(hyper::header::ACCESS_CONTROL_ALLOW_ORIGIN).unwrap(),
+    "http://example.com"
+  );
+}
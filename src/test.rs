@@ -0,0 +1,180 @@
+use super::*;
+
+#[test]
+fn http_auth_prefers_user_password_over_token() {
+  let auth = http_auth(Some("alice:secret"), Some("a-token"));
+  match auth {
+    Some(HttpAuth::User(user, password)) => {
+      assert_eq!(user, "alice");
+      assert_eq!(password, Some("secret".to_string()));
+    },
+    other => panic!("expected a User auth, got {:?}", other)
+  }
+}
+
+#[test]
+fn http_auth_falls_back_to_token() {
+  let auth = http_auth(None, Some("a-token"));
+  match auth {
+    Some(HttpAuth::Token(token)) => assert_eq!(token, "a-token"),
+    other => panic!("expected a Token auth, got {:?}", other)
+  }
+}
+
+#[test]
+fn http_auth_is_none_when_neither_is_given() {
+  assert!(http_auth(None, None).is_none());
+}
+
+#[test]
+fn consumer_version_selector_value_accepts_a_valid_selector() {
+  let result = consumer_version_selector_value("{\"mainBranch\": true}".to_string());
+  assert_eq!(result, Ok(()));
+}
+
+#[test]
+fn consumer_version_selector_value_rejects_valid_json_with_the_wrong_shape() {
+  let result = consumer_version_selector_value("true".to_string());
+  assert!(result.is_err());
+}
+
+#[test]
+fn build_app_generates_non_empty_completions_for_every_shell() {
+  for shell in Shell::variants() {
+    let mut out = Vec::new();
+    build_app("pact-stub-server", "0.0.0")
+      .gen_completions_to("pact-stub-server", shell.parse().unwrap(), &mut out);
+    assert!(!out.is_empty(), "expected a completion script for {}", shell);
+  }
+}
+
+#[test]
+fn consumer_version_selector_value_rejects_invalid_json() {
+  let result = consumer_version_selector_value("{not json".to_string());
+  assert!(result.is_err());
+}
+
+#[test]
+fn pact_source_combines_selectors_and_consumer_flags_for_a_broker_source() {
+  let app = build_app("pact-stub-server", "0.0.0");
+  let matches = app.get_matches_from_safe(vec![
+    "pact-stub-server",
+    "--broker-url", "http://broker",
+    "--provider", "a-provider",
+    "--consumer-version-selector", "{\"mainBranch\": true}",
+    "--consumer", "consumer-one",
+    "--consumer", "consumer-two"
+  ]).expect("arguments should parse");
+
+  let sources = pact_source(&matches);
+  assert_eq!(sources.len(), 1);
+  match &sources[0] {
+    PactSource::Broker { url, provider, selectors, .. } => {
+      assert_eq!(url, "http://broker");
+      assert_eq!(provider, &Some("a-provider".to_string()));
+      assert_eq!(selectors.len(), 3);
+      assert_eq!(selectors[0].main_branch, Some(true));
+      assert_eq!(selectors[1].consumer, Some("consumer-one".to_string()));
+      assert_eq!(selectors[2].consumer, Some("consumer-two".to_string()));
+    },
+    other => panic!("expected a Broker source, got {:?}", other)
+  }
+}
+
+#[test]
+fn pact_source_builds_a_webhook_callback_url_source_when_both_urls_are_given() {
+  let app = build_app("pact-stub-server", "0.0.0");
+  let matches = app.get_matches_from_safe(vec![
+    "pact-stub-server",
+    "--broker-url", "http://broker",
+    "--webhook-url", "http://broker/pacts/provider/a/consumer/b/pact-version/1"
+  ]).expect("arguments should parse");
+
+  let sources = pact_source(&matches);
+  assert_eq!(sources.len(), 1);
+  match &sources[0] {
+    PactSource::WebhookCallbackUrl { pact_url, broker_url, .. } => {
+      assert_eq!(pact_url, "http://broker/pacts/provider/a/consumer/b/pact-version/1");
+      assert_eq!(broker_url, "http://broker");
+    },
+    other => panic!("expected a WebhookCallbackUrl source, got {:?}", other)
+  }
+}
+
+#[test]
+fn source_config_to_pact_source_converts_each_variant() {
+  let file = SourceConfig::File { path: "pacts/a.json".to_string() };
+  assert!(matches!(source_config_to_pact_source(&file), PactSource::File(path) if path == "pacts/a.json"));
+
+  let dir = SourceConfig::Dir { path: "pacts".to_string() };
+  assert!(matches!(source_config_to_pact_source(&dir), PactSource::Dir(path) if path == "pacts"));
+
+  let url = SourceConfig::Url { url: "http://pact".to_string(), user: None, token: Some("a-token".to_string()) };
+  match source_config_to_pact_source(&url) {
+    PactSource::URL(url, Some(HttpAuth::Token(token))) => {
+      assert_eq!(url, "http://pact");
+      assert_eq!(token, "a-token");
+    },
+    other => panic!("expected a URL source with token auth, got {:?}", other)
+  }
+
+  let broker = SourceConfig::Broker {
+    url: "http://broker".to_string(),
+    user: Some("alice:secret".to_string()),
+    token: None,
+    provider: Some("a-provider".to_string()),
+    selectors: vec![ConsumerVersionSelector { latest: Some(true), ..ConsumerVersionSelector::default() }],
+    provider_version_branch: Some("main".to_string()),
+    include_pending: true
+  };
+  match source_config_to_pact_source(&broker) {
+    PactSource::Broker { url, provider, selectors, provider_version_branch, include_pending, .. } => {
+      assert_eq!(url, "http://broker");
+      assert_eq!(provider, Some("a-provider".to_string()));
+      assert_eq!(selectors.len(), 1);
+      assert_eq!(provider_version_branch, Some("main".to_string()));
+      assert!(include_pending);
+    },
+    other => panic!("expected a Broker source, got {:?}", other)
+  }
+
+  let webhook = SourceConfig::WebhookCallbackUrl {
+    pact_url: "http://broker/pact".to_string(),
+    broker_url: "http://broker".to_string(),
+    user: None,
+    token: None
+  };
+  assert!(matches!(source_config_to_pact_source(&webhook), PactSource::WebhookCallbackUrl { .. }));
+}
+
+#[test]
+fn load_config_file_parses_toml_by_extension() {
+  let path = std::env::temp_dir().join("pact-stub-server-test-config.toml");
+  fs::write(&path, "port = 1234\ncors = true\n\n[[sources]]\ntype = \"file\"\npath = \"pacts/a.json\"\n").unwrap();
+
+  let config = load_config_file(path.to_str().unwrap()).expect("config file should parse");
+  fs::remove_file(&path).ok();
+
+  assert_eq!(config.port, Some(1234));
+  assert!(config.cors);
+  assert_eq!(config.sources.len(), 1);
+}
+
+#[test]
+fn load_config_file_parses_yaml_by_extension() {
+  let path = std::env::temp_dir().join("pact-stub-server-test-config.yaml");
+  fs::write(&path, "port: 1234\ncors: true\nsources:\n  - type: file\n    path: pacts/a.json\n").unwrap();
+
+  let config = load_config_file(path.to_str().unwrap()).expect("config file should parse");
+  fs::remove_file(&path).ok();
+
+  assert_eq!(config.port, Some(1234));
+  assert!(config.cors);
+  assert_eq!(config.sources.len(), 1);
+}
+
+#[test]
+fn load_config_file_returns_an_error_for_a_missing_file() {
+  let result = load_config_file("/no/such/pact-stub-server-config.toml");
+  assert!(result.is_err());
+}